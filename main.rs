@@ -5,17 +5,85 @@ use solana_program::{
     entrypoint,
     entrypoint::ProgramResult,
     msg,
-    pubkey::Pubkey,
+    program::invoke_signed,
     program_error::ProgramError,
+    pubkey::Pubkey,
     system_instruction,
-    sysvar::{rent::Rent, Sysvar, clock::Clock},
-    program::invoke,
+    sysvar::{clock::Clock, rent::Rent, Sysvar},
 };
 
 // Constants to limit the size of names and records
 const MAX_NAME_LENGTH: usize = 64;
 const MAX_RECORD_SIZE: usize = 256;
 
+// Account and instruction-data validation shared by every instruction handler
+mod validation {
+    use solana_program::{account_info::AccountInfo, program_error::ProgramError, pubkey::Pubkey};
+
+    // `payer`, `name_account`, `system_program` — no instruction accepts more
+    pub const MAX_ACCOUNTS: usize = 3;
+
+    // Checks that apply before the instruction byte is even inspected: a
+    // non-empty payload and a bounded account list so extra attacker-supplied
+    // accounts are rejected rather than silently ignored by `next_account_info`
+    pub fn validate_instruction_shape(
+        accounts: &[AccountInfo],
+        instruction_data: &[u8],
+    ) -> Result<(), ProgramError> {
+        if instruction_data.is_empty() {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+        if accounts.len() > MAX_ACCOUNTS {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        Ok(())
+    }
+
+    pub fn require_signer(account: &AccountInfo) -> Result<(), ProgramError> {
+        if !account.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+        Ok(())
+    }
+
+    pub fn require_system_program(account: &AccountInfo) -> Result<(), ProgramError> {
+        if account.key != &solana_program::system_program::ID {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        Ok(())
+    }
+
+    // Only meaningful once a name account has actually been created; callers
+    // skip this for the Register instruction as a whole, including when it
+    // re-registers an existing account whose lease has lapsed
+    pub fn require_owned_by_program(
+        account: &AccountInfo,
+        program_id: &Pubkey,
+    ) -> Result<(), ProgramError> {
+        if account.owner != program_id {
+            return Err(ProgramError::IllegalOwner);
+        }
+        Ok(())
+    }
+}
+
+// Seed prefix used to derive every name account's PDA
+const NAME_SEED_PREFIX: &[u8] = b"name";
+
+// How long a registration lasts before it can be squatted over, in seconds.
+// One year; renewals add the same amount to the later of the current time
+// and the existing `expires_at`.
+const LEASE_SECONDS: i64 = 365 * 24 * 60 * 60;
+
+// The kinds of typed records a name can hold, alongside its owner/lease data
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq, Eq)]
+pub enum RecordType {
+    SolAddress,
+    Url,
+    Ipfs,
+    Text,
+}
+
 // NameRecord struct with derive macros for Borsh serialization/deserialization
 // and other useful traits for Solana program data structures
 #[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Default, PartialEq)]
@@ -23,6 +91,65 @@ pub struct NameRecord {
     pub name: String,
     pub owner: Pubkey,
     pub created_at: i64,
+    pub expires_at: i64,
+    pub bump: u8,
+    pub records: Vec<(RecordType, Vec<u8>)>,
+}
+
+// The on-chain layout before the leading version byte was introduced: a bare
+// `NameRecord` with no discriminator. Kept around so `migrate` can still read
+// accounts written by older deployments of this program.
+type NameRecordV0 = NameRecord;
+
+// Borsh encodes `NameRecord.name` (a `String`, its first field) as a 4-byte
+// LE length prefix followed by UTF-8 bytes, so a legacy (pre-versioning)
+// account's first byte is just the low byte of its name's length — always
+// `<= MAX_NAME_LENGTH`, since that's enforced on every write. Schema version
+// numbers therefore start strictly above `MAX_NAME_LENGTH`, so a legacy
+// account's first byte (at most `MAX_NAME_LENGTH`) can never collide with a
+// real version tag, no matter how short its name is (e.g. a 1-byte name).
+const SCHEMA_V1: u8 = MAX_NAME_LENGTH as u8 + 1;
+
+// The schema version this build of the program writes. Account data is laid
+// out as `[version: u8, borsh-serialized NameRecord]`.
+const CURRENT_SCHEMA_VERSION: u8 = SCHEMA_V1;
+
+// Reads a name account's data regardless of which schema version wrote it,
+// upgrading older layouts to the current `NameRecord` in memory. Handlers
+// that write the record back persist the upgrade as a side effect; `Migrate`
+// is the dedicated instruction for doing so without any other change.
+//
+// Matches on each historical version byte explicitly (via its own named
+// constant) rather than treating "not current" as "legacy": the schema-v1
+// arm stays valid once a future schema-v2 is added and
+// `CURRENT_SCHEMA_VERSION` moves on, instead of v1 accounts silently falling
+// into the no-discriminator legacy branch and having their version byte
+// parsed as record data.
+fn migrate(data: &[u8]) -> Result<NameRecord, ProgramError> {
+    if data.is_empty() {
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    match data[0] {
+        // Schema v1: current layout, `[SCHEMA_V1, borsh-serialized NameRecord]`
+        SCHEMA_V1 => {
+            NameRecord::deserialize(&mut &data[1..]).map_err(|_| ProgramError::InvalidAccountData)
+        }
+        // Pre-versioning accounts stored the record with no leading byte at all
+        _ => {
+            let legacy: NameRecordV0 = NameRecord::deserialize(&mut &data[..])
+                .map_err(|_| ProgramError::InvalidAccountData)?;
+            Ok(legacy)
+        }
+    }
+}
+
+// Writes `record` back to `name_account` under the current schema version
+fn write_versioned(name_account: &AccountInfo, record: &NameRecord) -> ProgramResult {
+    let mut data = name_account.data.borrow_mut();
+    data[0] = CURRENT_SCHEMA_VERSION;
+    record.serialize(&mut &mut data[1..])?;
+    Ok(())
 }
 
 // Declare the program's entrypoint
@@ -35,10 +162,7 @@ pub fn process_instruction<'a>(
     accounts: &'a [AccountInfo<'a>],
     instruction_data: &[u8],
 ) -> ProgramResult {
-    // Ensure we have some instruction data
-    if instruction_data.is_empty() {
-        return Err(ProgramError::InvalidInstructionData);
-    }
+    validation::validate_instruction_shape(accounts, instruction_data)?;
 
     // Parse the instruction type from the first byte
     let instruction = instruction_data[0];
@@ -46,20 +170,45 @@ pub fn process_instruction<'a>(
 
     // Iterator for the accounts
     let accounts_iter = &mut accounts.iter();
-    
+
     // Extract the required accounts
     let payer = next_account_info(accounts_iter)?;
     let name_account = next_account_info(accounts_iter)?;
     let system_program = next_account_info(accounts_iter)?;
 
+    validation::require_signer(payer)?;
+    validation::require_system_program(system_program)?;
+    // Register is the only instruction that may target a not-yet-created
+    // account, so it alone skips the ownership check
+    if instruction != 0 {
+        validation::require_owned_by_program(name_account, program_id)?;
+    }
+
     // Route to the appropriate instruction handler
     match instruction {
         0 => register_name(program_id, payer, name_account, system_program, name_data),
-        1 => resolve_name(name_account),
+        1 => resolve_name(program_id, name_account, name_data),
+        2 => update_name(program_id, payer, name_account, name_data),
+        3 => transfer_name(program_id, payer, name_account, name_data),
+        4 => close_name(program_id, payer, name_account),
+        5 => renew_name(program_id, payer, name_account),
+        6 => set_record(program_id, payer, name_account, name_data),
+        7 => migrate_name(program_id, payer, name_account),
         _ => Err(ProgramError::InvalidInstructionData),
     }
 }
 
+// Verifies that `authority` is a signer and matches the record's current owner
+fn check_authority(record: &NameRecord, authority: &AccountInfo) -> ProgramResult {
+    if !authority.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if authority.key != &record.owner {
+        return Err(ProgramError::IllegalOwner);
+    }
+    Ok(())
+}
+
 // Function to register a new name
 // We use the same lifetime 'a for all AccountInfo references to satisfy the borrow checker
 fn register_name<'a>(
@@ -75,51 +224,325 @@ fn register_name<'a>(
     }
 
     // Convert name bytes to string
-    let name = String::from_utf8(name_data.to_vec())
-        .map_err(|_| ProgramError::InvalidInstructionData)?;
+    let name =
+        String::from_utf8(name_data.to_vec()).map_err(|_| ProgramError::InvalidInstructionData)?;
+
+    // Derive the canonical PDA for this name and make sure the caller
+    // passed exactly that account, not an arbitrary one
+    let (derived_address, bump) =
+        Pubkey::find_program_address(&[NAME_SEED_PREFIX, name_data], program_id);
+    if derived_address != *name_account.key {
+        return Err(ProgramError::InvalidSeeds);
+    }
+
+    // A name can only be (re-)registered on a fresh account or one whose
+    // existing lease has lapsed; otherwise the current owner still holds it.
+    // Checked via `data_len()` rather than `lamports()`: anyone can transfer
+    // lamports into this PDA before it's created (a plain System Program
+    // transfer needs no signature from the PDA), which would otherwise make
+    // the account look "already registered" with no data to migrate.
+    if name_account.data_len() > 0 {
+        let existing = migrate(&name_account.data.borrow()[..])?;
+        validate_pda(program_id, name_account, &existing)?;
+        if Clock::get()?.unix_timestamp <= existing.expires_at {
+            return Err(ProgramError::AccountAlreadyInitialized);
+        }
+    } else {
+        // Calculate rent-exempt balance
+        let rent = Rent::get()?;
+        let space = MAX_RECORD_SIZE;
+        let rent_lamports = rent.minimum_balance(space);
 
-    // Calculate rent-exempt balance
-    let rent = Rent::get()?;
-    let space = MAX_RECORD_SIZE;
-    let rent_lamports = rent.minimum_balance(space);
-
-    // Create the name account
-    invoke(
-        &system_instruction::create_account(
-            payer.key,
-            name_account.key,
-            rent_lamports,
-            space as u64,
-            program_id,
-        ),
-        &[payer.clone(), name_account.clone(), system_program.clone()],
-    )?;
-
-    // Create and serialize the NameRecord
+        // Create the name account; since it's a PDA with no private key, the
+        // program itself must sign for it via invoke_signed
+        invoke_signed(
+            &system_instruction::create_account(
+                payer.key,
+                name_account.key,
+                rent_lamports,
+                space as u64,
+                program_id,
+            ),
+            &[payer.clone(), name_account.clone(), system_program.clone()],
+            &[&[NAME_SEED_PREFIX, name_data, &[bump]]],
+        )?;
+    }
+
+    // Create and serialize the NameRecord, (re-)starting the lease
+    let now = Clock::get()?.unix_timestamp;
     let record = NameRecord {
         name: name.clone(),
         owner: *payer.key,
-        created_at: Clock::get()?.unix_timestamp,
+        created_at: now,
+        expires_at: now + LEASE_SECONDS,
+        bump,
+        records: Vec::new(),
     };
 
-    // We use serialize directly as it's compatible with borsh 1.5.3
-    record.serialize(&mut &mut name_account.data.borrow_mut()[..])?;
+    write_versioned(name_account, &record)?;
 
     msg!("Registered name: {}", name);
     Ok(())
 }
 
-// Function to resolve a name
-fn resolve_name(name_account: &AccountInfo) -> ProgramResult {
-    // Deserialize the NameRecord from the account data
-    // We use deserialize directly as it's compatible with borsh 1.5.3
-    let record = NameRecord::deserialize(&mut &name_account.data.borrow()[..])?;
+// Function to resolve a name. If `name_data` carries a RecordType byte, only
+// that typed record is looked up and logged (GetRecord); otherwise the full
+// record summary is logged as before.
+fn resolve_name(
+    program_id: &Pubkey,
+    name_account: &AccountInfo,
+    name_data: &[u8],
+) -> ProgramResult {
+    let record = migrate(&name_account.data.borrow()[..])?;
+    validate_pda(program_id, name_account, &record)?;
+
+    // An expired name is still on-chain but no longer considered resolvable
+    if Clock::get()?.unix_timestamp > record.expires_at {
+        msg!("Name {} has expired", record.name);
+        return Ok(());
+    }
+
+    if !name_data.is_empty() {
+        let requested_type = RecordType::try_from_slice(&name_data[0..1])
+            .map_err(|_| ProgramError::InvalidInstructionData)?;
+        match record
+            .records
+            .iter()
+            .find(|(record_type, _)| record_type == &requested_type)
+        {
+            Some((_, value)) => msg!("{:?}: {:?}", requested_type, value),
+            None => msg!("{:?}: not set", requested_type),
+        }
+        return Ok(());
+    }
 
     // Log the name details
     msg!("Name: {}", record.name);
     msg!("Owner: {}", record.owner);
     msg!("Created at: {}", record.created_at);
+    msg!("Expires at: {}", record.expires_at);
+
+    Ok(())
+}
+
+// Computes a renewed `expires_at` given the current time and the record's
+// existing expiry. Extends from `now` if the lease already lapsed, rather
+// than from the stale `expires_at`; otherwise renewing a long-expired name
+// could still leave it expired (and squattable) right after a "successful"
+// renewal. Factored out of `renew_name` so tests can exercise this exact
+// arithmetic instead of a hand-copied formula.
+fn renewed_expiry(now: i64, expires_at: i64) -> i64 {
+    now.max(expires_at) + LEASE_SECONDS
+}
+
+// Function to extend an existing name's lease by one more lease period
+fn renew_name(
+    program_id: &Pubkey,
+    payer: &AccountInfo,
+    name_account: &AccountInfo,
+) -> ProgramResult {
+    let mut record = migrate(&name_account.data.borrow()[..])?;
+    check_authority(&record, payer)?;
+    validate_pda(program_id, name_account, &record)?;
+
+    let now = Clock::get()?.unix_timestamp;
+    record.expires_at = renewed_expiry(now, record.expires_at);
+    write_versioned(name_account, &record)?;
+
+    msg!("Renewed name {} until {}", record.name, record.expires_at);
+    Ok(())
+}
+
+// `name` is baked into this account's derived address (see chunk0-1's
+// `find_program_address`/`validate_pda`), so there is no such thing as
+// renaming a record in place: writing a new `name` to the same account would
+// desync the stored name from the address it actually lives at, and every
+// later instruction re-derives that address from the stored name before
+// trusting the account — including Close, permanently stranding it and its
+// rent. Renaming therefore has to go through Close followed by Register
+// under the new name; this handler only validates and refuses.
+fn update_name(
+    program_id: &Pubkey,
+    payer: &AccountInfo,
+    name_account: &AccountInfo,
+    name_data: &[u8],
+) -> ProgramResult {
+    if name_data.is_empty() || name_data.len() > MAX_NAME_LENGTH {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+
+    let record = migrate(&name_account.data.borrow()[..])?;
+    check_authority(&record, payer)?;
+    validate_pda(program_id, name_account, &record)?;
+
+    msg!(
+        "Cannot rename {} in place; close and re-register under the new name instead",
+        record.name
+    );
+    Err(ProgramError::InvalidInstructionData)
+}
+
+// Opcodes for the payload of a SetRecord instruction
+const SET_RECORD_OP_UPSERT: u8 = 0;
+const SET_RECORD_OP_DELETE: u8 = 1;
+
+// Applies a SetRecord mutation (insert/replace/delete) to `record` in place.
+// Factored out of `set_record` so tests can exercise this exact mutation
+// logic instead of reimplementing it separately.
+fn apply_set_record(
+    record: &mut NameRecord,
+    record_type: &RecordType,
+    op: u8,
+    value: &[u8],
+) -> ProgramResult {
+    record
+        .records
+        .retain(|(existing_type, _)| existing_type != record_type);
+    match op {
+        SET_RECORD_OP_UPSERT => {
+            if value.len() > MAX_RECORD_SIZE {
+                return Err(ProgramError::InvalidInstructionData);
+            }
+            record.records.push((record_type.clone(), value.to_vec()));
+        }
+        SET_RECORD_OP_DELETE => {}
+        _ => return Err(ProgramError::InvalidInstructionData),
+    }
+    Ok(())
+}
+
+// Function to insert, replace, or delete one typed record under a name.
+// Payload layout: [RecordType byte, op byte, value bytes (upsert only)]
+fn set_record(
+    program_id: &Pubkey,
+    payer: &AccountInfo,
+    name_account: &AccountInfo,
+    data: &[u8],
+) -> ProgramResult {
+    if data.len() < 2 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    let record_type = RecordType::try_from_slice(&data[0..1])
+        .map_err(|_| ProgramError::InvalidInstructionData)?;
+    let op = data[1];
+    let value = &data[2..];
+
+    let mut record = migrate(&name_account.data.borrow()[..])?;
+    check_authority(&record, payer)?;
+    validate_pda(program_id, name_account, &record)?;
+
+    apply_set_record(&mut record, &record_type, op, value)?;
+
+    // Make sure the updated record still fits in the account's allocated space
+    // (plus the one leading version byte every write carries)
+    let mut serialized = Vec::new();
+    record.serialize(&mut serialized)?;
+    if serialized.len() + 1 > name_account.data_len() {
+        return Err(ProgramError::AccountDataTooSmall);
+    }
+
+    write_versioned(name_account, &record)?;
+
+    msg!("Set record {:?} on name {}", record_type, record.name);
+    Ok(())
+}
+
+// Function to transfer ownership of a name record to a new owner
+fn transfer_name(
+    program_id: &Pubkey,
+    payer: &AccountInfo,
+    name_account: &AccountInfo,
+    new_owner_data: &[u8],
+) -> ProgramResult {
+    let new_owner =
+        Pubkey::try_from(new_owner_data).map_err(|_| ProgramError::InvalidInstructionData)?;
+
+    let mut record = migrate(&name_account.data.borrow()[..])?;
+    check_authority(&record, payer)?;
+    validate_pda(program_id, name_account, &record)?;
+
+    record.owner = new_owner;
+    write_versioned(name_account, &record)?;
+
+    msg!("Transferred name {} to {}", record.name, record.owner);
+    Ok(())
+}
+
+// Function to close a name record, reclaiming the rent back to the owner
+fn close_name(
+    program_id: &Pubkey,
+    payer: &AccountInfo,
+    name_account: &AccountInfo,
+) -> ProgramResult {
+    let record = migrate(&name_account.data.borrow()[..])?;
+    check_authority(&record, payer)?;
+    validate_pda(program_id, name_account, &record)?;
+
+    // Move all lamports back to the owner and zero out the data
+    let owner_starting_lamports = payer.lamports();
+    **payer.lamports.borrow_mut() = owner_starting_lamports
+        .checked_add(name_account.lamports())
+        .ok_or(ProgramError::ArithmeticOverflow)?;
+    **name_account.lamports.borrow_mut() = 0;
+    name_account.data.borrow_mut().fill(0);
+    name_account.resize(0)?;
+    // Hand the account back to the System Program so the name can be
+    // re-registered later: `create_account` refuses any target that isn't
+    // already owned by the System Program, even at zero lamports/data.
+    name_account.assign(&solana_program::system_program::ID);
+
+    msg!("Closed name: {}", record.name);
+    Ok(())
+}
+
+// Function to persist an upgraded schema layout back to an account, growing
+// its allocation first if the current schema no longer fits
+fn migrate_name(
+    program_id: &Pubkey,
+    payer: &AccountInfo,
+    name_account: &AccountInfo,
+) -> ProgramResult {
+    let record = migrate(&name_account.data.borrow()[..])?;
+    check_authority(&record, payer)?;
+    validate_pda(program_id, name_account, &record)?;
 
+    let mut serialized = Vec::new();
+    record.serialize(&mut serialized)?;
+    let required_len = 1 + serialized.len();
+
+    if required_len > name_account.data_len() {
+        let rent = Rent::get()?;
+        if name_account.lamports() < rent.minimum_balance(required_len) {
+            return Err(ProgramError::InsufficientFunds);
+        }
+        name_account.resize(required_len)?;
+    }
+
+    write_versioned(name_account, &record)?;
+
+    msg!(
+        "Migrated name {} to schema version byte {}",
+        record.name,
+        CURRENT_SCHEMA_VERSION
+    );
+    Ok(())
+}
+
+// Re-derives a record's PDA from its stored name and bump, confirming
+// `name_account` is actually the canonical account for that name
+fn validate_pda(
+    program_id: &Pubkey,
+    name_account: &AccountInfo,
+    record: &NameRecord,
+) -> ProgramResult {
+    let derived_address = Pubkey::create_program_address(
+        &[NAME_SEED_PREFIX, record.name.as_bytes(), &[record.bump]],
+        program_id,
+    )
+    .map_err(|_| ProgramError::InvalidSeeds)?;
+    if derived_address != *name_account.key {
+        return Err(ProgramError::InvalidSeeds);
+    }
     Ok(())
 }
 
@@ -135,6 +558,9 @@ mod tests {
             name: "example.sol".to_string(),
             owner: Pubkey::new_unique(),
             created_at: 1234567890,
+            expires_at: 1234567890 + LEASE_SECONDS,
+            bump: 255,
+            records: vec![(RecordType::Url, b"https://example.com".to_vec())],
         };
 
         // Serialize the record
@@ -148,6 +574,8 @@ mod tests {
         assert_eq!(record.name, decoded.name);
         assert_eq!(record.owner, decoded.owner);
         assert_eq!(record.created_at, decoded.created_at);
+        assert_eq!(record.expires_at, decoded.expires_at);
+        assert_eq!(record.bump, decoded.bump);
     }
 
     #[test]
@@ -158,9 +586,299 @@ mod tests {
             name: long_name,
             owner: Pubkey::new_unique(),
             created_at: 0,
+            expires_at: LEASE_SECONDS,
+            bump: 0,
+            records: Vec::new(),
         };
 
         // Assert that the name length exceeds the maximum allowed length
         assert!(record.name.len() > MAX_NAME_LENGTH);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_renewal_extends_from_existing_expiry() {
+        // Renewal should extend the existing lease, not reset it from now
+        let created_at = 1_000_000;
+        let expires_at = created_at + LEASE_SECONDS;
+        let now = created_at; // well before the lease has lapsed
+
+        assert_eq!(renewed_expiry(now, expires_at), expires_at + LEASE_SECONDS);
+    }
+
+    #[test]
+    fn test_renewal_extends_from_now_once_already_expired() {
+        // A lease that's been expired for a while should renew from now, not
+        // from its stale expires_at, or the result could still be in the past
+        let created_at = 0;
+        let expires_at = created_at + LEASE_SECONDS;
+        let now = created_at + LEASE_SECONDS * 3;
+
+        let renewed = renewed_expiry(now, expires_at);
+
+        assert_eq!(renewed, now + LEASE_SECONDS);
+        assert!(renewed > now);
+    }
+
+    #[test]
+    fn test_update_name_refuses_to_rename_and_leaves_the_account_valid() {
+        let program_id = Pubkey::new_unique();
+        let owner = Pubkey::new_unique();
+
+        let name = b"example.sol";
+        let (name_pda, bump) =
+            Pubkey::find_program_address(&[NAME_SEED_PREFIX, name], &program_id);
+        let record = NameRecord {
+            name: String::from_utf8(name.to_vec()).unwrap(),
+            owner,
+            created_at: 0,
+            expires_at: LEASE_SECONDS,
+            bump,
+            records: Vec::new(),
+        };
+
+        let mut account_data = vec![0u8; MAX_RECORD_SIZE];
+        account_data[0] = CURRENT_SCHEMA_VERSION;
+        let mut serialized = Vec::new();
+        record.serialize(&mut serialized).unwrap();
+        account_data[1..1 + serialized.len()].copy_from_slice(&serialized);
+
+        let mut payer_lamports = 0u64;
+        let mut payer_data: Vec<u8> = Vec::new();
+        let system_program_id = solana_program::system_program::ID;
+        let payer = AccountInfo::new(
+            &owner,
+            true,
+            false,
+            &mut payer_lamports,
+            &mut payer_data,
+            &system_program_id,
+            false,
+            0,
+        );
+
+        let mut name_lamports = 1u64;
+        let name_account = AccountInfo::new(
+            &name_pda,
+            false,
+            true,
+            &mut name_lamports,
+            &mut account_data,
+            &program_id,
+            false,
+            0,
+        );
+
+        let result = update_name(&program_id, &payer, &name_account, b"renamed.sol");
+        assert_eq!(result, Err(ProgramError::InvalidInstructionData));
+
+        // The account must be untouched: its PDA should still validate against
+        // the original name, so later instructions (e.g. Close) can still
+        // locate and act on it.
+        let reread = migrate(&name_account.data.borrow()[..]).unwrap();
+        assert_eq!(reread, record);
+        assert!(validate_pda(&program_id, &name_account, &reread).is_ok());
+    }
+
+    #[test]
+    fn test_name_pda_is_deterministic() {
+        // The same program id and name must always derive the same address
+        let program_id = Pubkey::new_unique();
+        let name = b"example.sol";
+
+        let (address_one, bump_one) =
+            Pubkey::find_program_address(&[NAME_SEED_PREFIX, name], &program_id);
+        let (address_two, bump_two) =
+            Pubkey::find_program_address(&[NAME_SEED_PREFIX, name], &program_id);
+
+        assert_eq!(address_one, address_two);
+        assert_eq!(bump_one, bump_two);
+    }
+
+    #[test]
+    fn test_record_type_round_trips_as_a_single_byte() {
+        // SetRecord/GetRecord encode RecordType as the first payload byte
+        for record_type in [
+            RecordType::SolAddress,
+            RecordType::Url,
+            RecordType::Ipfs,
+            RecordType::Text,
+        ] {
+            let mut encoded = Vec::new();
+            record_type.serialize(&mut encoded).unwrap();
+            assert_eq!(encoded.len(), 1);
+
+            let decoded = RecordType::try_from_slice(&encoded[..1]).unwrap();
+            assert_eq!(record_type, decoded);
+        }
+    }
+
+    #[test]
+    fn test_set_record_replaces_existing_entry_of_same_type() {
+        let mut record = NameRecord {
+            name: "example.sol".to_string(),
+            owner: Pubkey::new_unique(),
+            created_at: 0,
+            expires_at: LEASE_SECONDS,
+            bump: 0,
+            records: vec![(RecordType::Url, b"old".to_vec())],
+        };
+
+        apply_set_record(&mut record, &RecordType::Url, SET_RECORD_OP_UPSERT, b"new").unwrap();
+
+        assert_eq!(record.records, vec![(RecordType::Url, b"new".to_vec())]);
+    }
+
+    #[test]
+    fn test_set_record_delete_removes_entry() {
+        let mut record = NameRecord {
+            name: "example.sol".to_string(),
+            owner: Pubkey::new_unique(),
+            created_at: 0,
+            expires_at: LEASE_SECONDS,
+            bump: 0,
+            records: vec![(RecordType::Url, b"old".to_vec())],
+        };
+
+        apply_set_record(&mut record, &RecordType::Url, SET_RECORD_OP_DELETE, b"").unwrap();
+
+        assert_eq!(record.records, Vec::new());
+    }
+
+    #[test]
+    fn test_migrate_reads_current_versioned_layout() {
+        let record = NameRecord {
+            name: "example.sol".to_string(),
+            owner: Pubkey::new_unique(),
+            created_at: 0,
+            expires_at: LEASE_SECONDS,
+            bump: 7,
+            records: Vec::new(),
+        };
+
+        let mut data = vec![CURRENT_SCHEMA_VERSION];
+        record.serialize(&mut data).unwrap();
+
+        let migrated = migrate(&data).unwrap();
+        assert_eq!(migrated, record);
+    }
+
+    #[test]
+    fn test_migrate_reads_legacy_unversioned_layout() {
+        // Pre-versioning accounts have no leading discriminator byte at all
+        let record = NameRecord {
+            name: "example.sol".to_string(),
+            owner: Pubkey::new_unique(),
+            created_at: 0,
+            expires_at: LEASE_SECONDS,
+            bump: 7,
+            records: Vec::new(),
+        };
+
+        let mut data = Vec::new();
+        record.serialize(&mut data).unwrap();
+        // The first byte of a legacy record just happens to collide with the
+        // current version tag here only if it matches CURRENT_SCHEMA_VERSION;
+        // assert the fixture is actually exercising the legacy branch
+        assert_ne!(data[0], CURRENT_SCHEMA_VERSION);
+
+        let migrated = migrate(&data).unwrap();
+        assert_eq!(migrated, record);
+    }
+
+    #[test]
+    fn test_migrate_reads_legacy_unversioned_layout_with_single_byte_name() {
+        // A legacy record whose name is exactly 1 byte encodes its Borsh
+        // length prefix's low byte as 1 — the exact value SCHEMA_V1 would
+        // have taken if version numbers weren't kept above MAX_NAME_LENGTH.
+        // This is the collision `migrate` must not misparse as schema v1.
+        let record = NameRecord {
+            name: "a".to_string(),
+            owner: Pubkey::new_unique(),
+            created_at: 0,
+            expires_at: LEASE_SECONDS,
+            bump: 7,
+            records: Vec::new(),
+        };
+
+        let mut data = Vec::new();
+        record.serialize(&mut data).unwrap();
+        assert_eq!(data[0], 1);
+        assert_ne!(data[0], SCHEMA_V1);
+
+        let migrated = migrate(&data).unwrap();
+        assert_eq!(migrated, record);
+    }
+
+    #[test]
+    fn test_validate_instruction_shape_rejects_empty_data() {
+        let accounts: Vec<AccountInfo> = Vec::new();
+        let result = super::validation::validate_instruction_shape(&accounts, &[]);
+        assert_eq!(result, Err(ProgramError::InvalidInstructionData));
+    }
+
+    // Shared fixture for the `validation` tests below: a non-signer,
+    // non-writable, non-executable account with no data, owned by `owner`.
+    fn non_signer_account<'a>(
+        key: &'a Pubkey,
+        owner: &'a Pubkey,
+        lamports: &'a mut u64,
+        data: &'a mut [u8],
+    ) -> AccountInfo<'a> {
+        AccountInfo::new(key, false, false, lamports, data, owner, false, 0)
+    }
+
+    #[test]
+    fn test_validate_instruction_shape_rejects_too_many_accounts() {
+        let key = Pubkey::new_unique();
+        let owner = Pubkey::new_unique();
+        let mut lamports = 0u64;
+        let mut data: Vec<u8> = Vec::new();
+        let account = non_signer_account(&key, &owner, &mut lamports, &mut data);
+        let accounts = vec![
+            account.clone(),
+            account.clone(),
+            account.clone(),
+            account.clone(),
+        ];
+
+        let result = super::validation::validate_instruction_shape(&accounts, &[0]);
+        assert_eq!(result, Err(ProgramError::InvalidAccountData));
+    }
+
+    #[test]
+    fn test_require_signer_rejects_non_signer() {
+        let key = Pubkey::new_unique();
+        let owner = Pubkey::new_unique();
+        let mut lamports = 0u64;
+        let mut data: Vec<u8> = Vec::new();
+        let account = non_signer_account(&key, &owner, &mut lamports, &mut data);
+
+        let result = super::validation::require_signer(&account);
+        assert_eq!(result, Err(ProgramError::MissingRequiredSignature));
+    }
+
+    #[test]
+    fn test_require_system_program_rejects_wrong_program_id() {
+        let key = Pubkey::new_unique();
+        let owner = Pubkey::new_unique();
+        let mut lamports = 0u64;
+        let mut data: Vec<u8> = Vec::new();
+        let account = non_signer_account(&key, &owner, &mut lamports, &mut data);
+
+        let result = super::validation::require_system_program(&account);
+        assert_eq!(result, Err(ProgramError::IncorrectProgramId));
+    }
+
+    #[test]
+    fn test_require_owned_by_program_rejects_foreign_owner() {
+        let key = Pubkey::new_unique();
+        let owner = Pubkey::new_unique();
+        let program_id = Pubkey::new_unique();
+        let mut lamports = 0u64;
+        let mut data: Vec<u8> = Vec::new();
+        let account = non_signer_account(&key, &owner, &mut lamports, &mut data);
+
+        let result = super::validation::require_owned_by_program(&account, &program_id);
+        assert_eq!(result, Err(ProgramError::IllegalOwner));
+    }
+}